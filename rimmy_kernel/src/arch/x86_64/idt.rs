@@ -20,6 +20,9 @@ lazy_static! {
             idt.double_fault
                 .set_handler_fn(double_fault_handler)
                 .set_stack_index(gdt::DOUBLE_FAULT_IST_INDEX);
+            idt.non_maskable_interrupt
+                .set_handler_fn(nmi_handler)
+                .set_stack_index(gdt::NMI_IST_INDEX);
         }
         idt[interrupt_index(0)].set_handler_fn(timer_interrupt_handler);
         idt[interrupt_index(1)].set_handler_fn(keyboard_interrupt_handler);
@@ -39,6 +42,24 @@ extern "x86-interrupt" fn double_fault_handler(stack_frame: InterruptStackFrame,
     panic!("EXCEPTION: DOUBLE FAULT\n{:#?}\nERROR CODE: {:#?}", stack_frame.instruction_pointer.as_u64(), err);
 }
 
+const SYSTEM_CONTROL_PORT_B: u16 = 0x61;
+const PORT_B_SERR: u8 = 1 << 7;
+const PORT_B_IOCHK: u8 = 1 << 6;
+
+// NMIs can't be masked, so only hardware errors are reported (and are fatal),
+// anything else returns without touching the writer or allocator locks
+extern "x86-interrupt" fn nmi_handler(stack_frame: InterruptStackFrame) {
+    let status = unsafe { Port::<u8>::new(SYSTEM_CONTROL_PORT_B).read() };
+    if status & (PORT_B_SERR | PORT_B_IOCHK) != 0 {
+        panic!(
+            "EXCEPTION: NMI at {:#x}, SERR: {}, IOCHK: {}",
+            stack_frame.instruction_pointer.as_u64(),
+            status & PORT_B_SERR != 0,
+            status & PORT_B_IOCHK != 0
+        );
+    }
+}
+
 extern "x86-interrupt" fn general_protection_fault_handler(
     stack_frame: InterruptStackFrame,
     error_code: u64,
@@ -58,6 +79,7 @@ extern "x86-interrupt" fn page_fault_handler(_stack_frame: InterruptStackFrame,
 }
 
 use spin::Mutex;
+use x86_64::instructions::port::Port;
 use x86_64::registers::control::Cr2;
 
 pub const PIC_1_OFFSET: u8 = 32;
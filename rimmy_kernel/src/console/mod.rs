@@ -1,16 +1,19 @@
 extern crate alloc;
 
 use crate::{print, println};
-use alloc::string::String;
 use alloc::vec::Vec;
 use futures_util::{StreamExt};
 use spin::Mutex;
 use crate::buffer::stdin::{StdinStream};
+use crate::console::readline::ReadLine;
 use crate::task::executor::{EXECUTOR};
 use crate::task::Task;
 
-pub static STDIO: Mutex<String> = Mutex::new(String::new());
-pub static CURSOR_POSITION: Mutex<usize> = Mutex::new(0);
+pub mod readline;
+
+const PROMPT: &str = "[rimmy] <- ";
+
+pub static READLINE: Mutex<ReadLine> = Mutex::new(ReadLine::new());
 
 async fn handle_input() {
     while let Some(c) = StdinStream::new().next().await {
@@ -23,16 +26,21 @@ pub fn init_console() {
 }
 
 pub fn start_kernel_console() {
-    print!("[rimmy] <- ");
-    let mut cur_pos = CURSOR_POSITION.lock();
-    *cur_pos = 2;
+    READLINE.lock().redraw(PROMPT, true);
 }
 
 fn handle_console_key(c: char) {
     match c {
         '\n' => {
-            print!("\n");
-            let mut cmd_line = STDIO.lock();
+            let cmd_line = {
+                let mut readline = READLINE.lock();
+                // take the cursor off the finished line
+                readline.redraw(PROMPT, false);
+                print!("\n");
+                let cmd_line = readline.as_string();
+                readline.clear();
+                cmd_line
+            };
             let args: Vec<&str> = cmd_line.split_whitespace().collect();
 
             if args.len() > 1 {
@@ -40,24 +48,17 @@ fn handle_console_key(c: char) {
             } else if !args.is_empty() {
                 exec(args[0], &[]);
             }
-            cmd_line.clear();
             start_kernel_console();
         }
-        '\x08' => {
-            if *CURSOR_POSITION.lock() > 2 {
-                print!("{}", c);
-                let mut cmd_line = STDIO.lock();
-                if cmd_line.trim().len() > 0 {
-                    cmd_line.pop();
-                }
-                *CURSOR_POSITION.lock() -= 1;
-            }
-        }
         _ => {
-            print!("{}", c);
-            STDIO.lock().push(c);
-            let mut cur_pos = CURSOR_POSITION.lock();
-            *cur_pos += 1;
+            // keep the prompt, line and cursor cell on one row, redraw can't follow a wrapped line
+            let columns = (crate::framebuffer::get_framebuffer().width() / 8) as usize;
+            let max_len = columns.saturating_sub(PROMPT.len() + 2);
+
+            let mut readline = READLINE.lock();
+            if readline.handle_key(c, max_len) {
+                readline.redraw(PROMPT, true);
+            }
         }
     };
 }
@@ -66,13 +67,14 @@ fn exec(cmd: &str, args: &[&str]) {
     match cmd {
         "echo" => crate::kernel_utils::echo::main(args),
         "clear" => {
-            crate::framebuffer::clear_screen(true);
+            crate::framebuffer::clear_screen();
         }
         "uptime" => {
             println!("{:.6} seconds", crate::driver::timer::pit::uptime());
         }
         "date" => crate::kernel_utils::date::main(),
         "meminfo" => crate::kernel_utils::meminfo::main(),
+        "nvram" => crate::kernel_utils::nvram::main(args),
         "uname" => {
             println!("Rimmy-Kernel 0.1 DevBuild")
         }
@@ -0,0 +1,140 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::Mutex;
+use crate::print;
+
+// text removed by Ctrl+W/K/U, inserted again by Ctrl+Y
+pub static KILL_BUFFER: Mutex<String> = Mutex::new(String::new());
+
+pub struct ReadLine {
+    pub line: Vec<char>,
+    pub cursor: usize,
+}
+
+impl Default for ReadLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadLine {
+    pub const fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            cursor: 0,
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        self.line.iter().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.line.clear();
+        self.cursor = 0;
+    }
+
+    /// Applies a key to the line, returns `false` if the key was ignored.
+    /// Nothing is inserted past `max_len` characters, so the line never wraps.
+    pub fn handle_key(&mut self, c: char, max_len: usize) -> bool {
+        match c {
+            // Ctrl+A
+            '\x01' => self.cursor = 0,
+            // Ctrl+E
+            '\x05' => self.cursor = self.line.len(),
+            // Ctrl+B
+            '\x02' => self.cursor = self.cursor.saturating_sub(1),
+            // Ctrl+F
+            '\x06' => self.cursor = (self.cursor + 1).min(self.line.len()),
+            // Ctrl+W
+            '\x17' => self.kill_previous_word(),
+            // Ctrl+K
+            '\x0b' => {
+                let killed = self.line.split_off(self.cursor);
+                kill(&killed);
+            }
+            // Ctrl+U
+            '\x15' => {
+                let killed: Vec<char> = self.line.drain(..self.cursor).collect();
+                self.cursor = 0;
+                kill(&killed);
+            }
+            // Ctrl+Y
+            '\x19' => self.yank(max_len),
+            '\x08' => {
+                if self.cursor == 0 {
+                    return false;
+                }
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            // the writer renders tabs as 4 columns, keep them as spaces in the line
+            '\t' => {
+                let room = max_len.saturating_sub(self.line.len()).min(4);
+                if room == 0 {
+                    return false;
+                }
+                for _ in 0..room {
+                    self.line.insert(self.cursor, ' ');
+                    self.cursor += 1;
+                }
+            }
+            _ if c.is_control() || self.line.len() >= max_len => return false,
+            _ => {
+                self.line.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+        }
+        true
+    }
+
+    fn kill_previous_word(&mut self) {
+        let mut start = self.cursor;
+        while start > 0 && self.line[start - 1] == ' ' {
+            start -= 1;
+        }
+        while start > 0 && self.line[start - 1] != ' ' {
+            start -= 1;
+        }
+
+        let killed: Vec<char> = self.line.drain(start..self.cursor).collect();
+        self.cursor = start;
+        kill(&killed);
+    }
+
+    fn yank(&mut self, max_len: usize) {
+        let room = max_len.saturating_sub(self.line.len());
+        for c in KILL_BUFFER.lock().chars().take(room) {
+            self.line.insert(self.cursor, c);
+            self.cursor += 1;
+        }
+    }
+
+    /// Reprints the prompt and line, then moves the cursor back to its column.
+    /// With `show_cursor` the cell under the cursor is drawn in reverse video.
+    pub fn redraw(&self, prompt: &str, show_cursor: bool) {
+        let before: String = self.line[..self.cursor].iter().collect();
+        let after: String = self.line[self.cursor..].iter().collect();
+        print!("\r{}{}", prompt, before);
+
+        if show_cursor {
+            let mut rest = after.chars();
+            let under_cursor = rest.next().unwrap_or(' ');
+            print!("\x1b[7m{}\x1b[27m{}", under_cursor, rest.as_str());
+        } else {
+            print!("{}", after);
+        }
+        print!("\x1b[K\r");
+
+        let column = prompt.len() + self.cursor;
+        if column > 0 {
+            print!("\x1b[{}C", column);
+        }
+    }
+}
+
+fn kill(text: &[char]) {
+    if !text.is_empty() {
+        *KILL_BUFFER.lock() = text.iter().collect();
+    }
+}
@@ -1,10 +1,11 @@
+use alloc::boxed::Box;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 use conquer_once::spin::OnceCell;
 use crossbeam_queue::ArrayQueue;
 use futures_util::{Stream, StreamExt};
 use futures_util::task::AtomicWaker;
-use pc_keyboard::{layouts, DecodedKey, HandleControl, Keyboard, ScancodeSet1};
+use pc_keyboard::{layouts, DecodedKey, EventDecoder, HandleControl, ScancodeSet, ScancodeSet1};
 use crate::{println, print};
 
 pub mod ps2;
@@ -26,13 +27,17 @@ pub(crate) fn add_scancode(scancode: u8) {
 
 pub async fn keyboard_interrupt() {
     let mut scancodes = ScancodeStream::new();
-    let mut keyboard = Keyboard::new(ScancodeSet1::new(),
-                                     layouts::Us104Key, HandleControl::Ignore);
+    let mut scancode_set: Box<dyn ScancodeSet> = if ps2::scancode_set_2_enabled() {
+        Box::new(ps2::ScancodeParser::new())
+    } else {
+        Box::new(ScancodeSet1::new())
+    };
+    let mut decoder = EventDecoder::new(layouts::Us104Key, HandleControl::MapLettersToUnicode);
 
 
     while let Some(scancode) = scancodes.next().await {
-        if let Ok(Some(key_event)) = keyboard.add_byte(scancode) {
-            if let Some(key) = keyboard.process_keyevent(key_event) {
+        if let Ok(Some(key_event)) = scancode_set.advance_state(scancode) {
+            if let Some(key) = decoder.process_keyevent(key_event) {
                 match key {
                     DecodedKey::Unicode(character) => send_char(character),
                     DecodedKey::RawKey(_key) => {},
@@ -0,0 +1,230 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+use pc_keyboard::{Error, KeyCode, KeyEvent, KeyState, ScancodeSet};
+use x86_64::instructions::port::Port;
+
+const DATA_PORT: u16 = 0x60;
+const STATUS_PORT: u16 = 0x64;
+const COMMAND_PORT: u16 = 0x64;
+
+const READ_CONFIG: u8 = 0x20;
+const WRITE_CONFIG: u8 = 0x60;
+const CONFIG_TRANSLATION: u8 = 1 << 6;
+
+const SET_SCANCODE_SET: u8 = 0xF0;
+const ACK: u8 = 0xFA;
+const RESEND: u8 = 0xFE;
+
+const EXTENDED: u8 = 0xE0;
+const PAUSE: u8 = 0xE1;
+const RELEASE: u8 = 0xF0;
+
+const TIMEOUT: usize = 100_000;
+
+static SCANCODE_SET_2: AtomicBool = AtomicBool::new(false);
+
+static SET2_KEYS: [Option<KeyCode>; 256] = build_table(&[
+    (0x01, KeyCode::F9), (0x03, KeyCode::F5), (0x04, KeyCode::F3), (0x05, KeyCode::F1),
+    (0x06, KeyCode::F2), (0x07, KeyCode::F12), (0x09, KeyCode::F10), (0x0A, KeyCode::F8),
+    (0x0B, KeyCode::F6), (0x0C, KeyCode::F4), (0x0D, KeyCode::Tab), (0x0E, KeyCode::Oem8),
+    (0x11, KeyCode::LAlt), (0x12, KeyCode::LShift), (0x14, KeyCode::LControl), (0x15, KeyCode::Q),
+    (0x16, KeyCode::Key1), (0x1A, KeyCode::Z), (0x1B, KeyCode::S), (0x1C, KeyCode::A),
+    (0x1D, KeyCode::W), (0x1E, KeyCode::Key2), (0x21, KeyCode::C), (0x22, KeyCode::X),
+    (0x23, KeyCode::D), (0x24, KeyCode::E), (0x25, KeyCode::Key4), (0x26, KeyCode::Key3),
+    (0x29, KeyCode::Spacebar), (0x2A, KeyCode::V), (0x2B, KeyCode::F), (0x2C, KeyCode::T),
+    (0x2D, KeyCode::R), (0x2E, KeyCode::Key5), (0x31, KeyCode::N), (0x32, KeyCode::B),
+    (0x33, KeyCode::H), (0x34, KeyCode::G), (0x35, KeyCode::Y), (0x36, KeyCode::Key6),
+    (0x3A, KeyCode::M), (0x3B, KeyCode::J), (0x3C, KeyCode::U), (0x3D, KeyCode::Key7),
+    (0x3E, KeyCode::Key8), (0x41, KeyCode::OemComma), (0x42, KeyCode::K), (0x43, KeyCode::I),
+    (0x44, KeyCode::O), (0x45, KeyCode::Key0), (0x46, KeyCode::Key9), (0x49, KeyCode::OemPeriod),
+    (0x4A, KeyCode::Oem2), (0x4B, KeyCode::L), (0x4C, KeyCode::Oem1), (0x4D, KeyCode::P),
+    (0x4E, KeyCode::OemMinus), (0x52, KeyCode::Oem3), (0x54, KeyCode::Oem4), (0x55, KeyCode::OemPlus),
+    (0x58, KeyCode::CapsLock), (0x59, KeyCode::RShift), (0x5A, KeyCode::Return), (0x5B, KeyCode::Oem6),
+    (0x5D, KeyCode::Oem7), (0x61, KeyCode::Oem5), (0x66, KeyCode::Backspace), (0x69, KeyCode::Numpad1),
+    (0x6B, KeyCode::Numpad4), (0x6C, KeyCode::Numpad7), (0x70, KeyCode::Numpad0), (0x71, KeyCode::NumpadPeriod),
+    (0x72, KeyCode::Numpad2), (0x73, KeyCode::Numpad5), (0x74, KeyCode::Numpad6), (0x75, KeyCode::Numpad8),
+    (0x76, KeyCode::Escape), (0x77, KeyCode::NumpadLock), (0x78, KeyCode::F11), (0x79, KeyCode::NumpadAdd),
+    (0x7A, KeyCode::Numpad3), (0x7B, KeyCode::NumpadSubtract), (0x7C, KeyCode::NumpadMultiply), (0x7D, KeyCode::Numpad9),
+    (0x7E, KeyCode::ScrollLock), (0x83, KeyCode::F7),
+]);
+
+static SET2_EXTENDED_KEYS: [Option<KeyCode>; 256] = build_table(&[
+    (0x11, KeyCode::RAltGr), (0x14, KeyCode::RControl), (0x1F, KeyCode::LWin), (0x27, KeyCode::RWin),
+    (0x2F, KeyCode::Apps), (0x4A, KeyCode::NumpadDivide), (0x5A, KeyCode::NumpadEnter), (0x69, KeyCode::End),
+    (0x6B, KeyCode::ArrowLeft), (0x6C, KeyCode::Home), (0x70, KeyCode::Insert), (0x71, KeyCode::Delete),
+    (0x72, KeyCode::ArrowDown), (0x74, KeyCode::ArrowRight), (0x75, KeyCode::ArrowUp), (0x7A, KeyCode::PageDown),
+    (0x7C, KeyCode::PrintScreen), (0x7D, KeyCode::PageUp),
+]);
+
+const fn build_table(entries: &[(u8, KeyCode)]) -> [Option<KeyCode>; 256] {
+    let mut table = [None; 256];
+    let mut i = 0;
+    while i < entries.len() {
+        table[entries[i].0 as usize] = Some(entries[i].1);
+        i += 1;
+    }
+    table
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ParserState {
+    Start,
+    Release,
+    Extended,
+    ExtendedRelease,
+    // Pause sends E1 14 77 E1 F0 14 F0 77 with no matching key, skip it
+    Pause(u8),
+}
+
+/// Decodes scancode set 2 bytes, including 0xE0 extended keys and 0xF0 break codes.
+pub struct ScancodeParser {
+    state: ParserState,
+}
+
+impl Default for ScancodeParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScancodeParser {
+    pub const fn new() -> Self {
+        Self { state: ParserState::Start }
+    }
+
+    fn key_event(table: &[Option<KeyCode>; 256], code: u8, state: KeyState) -> Result<Option<KeyEvent>, Error> {
+        match table[code as usize] {
+            Some(key) => Ok(Some(KeyEvent::new(key, state))),
+            None => Err(Error::UnknownKeyCode),
+        }
+    }
+}
+
+impl ScancodeSet for ScancodeParser {
+    fn advance_state(&mut self, code: u8) -> Result<Option<KeyEvent>, Error> {
+        match self.state {
+            ParserState::Start => match code {
+                EXTENDED => {
+                    self.state = ParserState::Extended;
+                    Ok(None)
+                }
+                RELEASE => {
+                    self.state = ParserState::Release;
+                    Ok(None)
+                }
+                PAUSE => {
+                    self.state = ParserState::Pause(7);
+                    Ok(None)
+                }
+                _ => Self::key_event(&SET2_KEYS, code, KeyState::Down),
+            },
+            ParserState::Release => {
+                self.state = ParserState::Start;
+                Self::key_event(&SET2_KEYS, code, KeyState::Up)
+            }
+            ParserState::Extended => match code {
+                RELEASE => {
+                    self.state = ParserState::ExtendedRelease;
+                    Ok(None)
+                }
+                _ => {
+                    self.state = ParserState::Start;
+                    Self::key_event(&SET2_EXTENDED_KEYS, code, KeyState::Down)
+                }
+            },
+            ParserState::ExtendedRelease => {
+                self.state = ParserState::Start;
+                Self::key_event(&SET2_EXTENDED_KEYS, code, KeyState::Up)
+            }
+            ParserState::Pause(remaining) => {
+                self.state = if remaining > 1 { ParserState::Pause(remaining - 1) } else { ParserState::Start };
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn status() -> u8 {
+    unsafe { Port::<u8>::new(STATUS_PORT).read() }
+}
+
+fn wait_input_empty() -> bool {
+    (0..TIMEOUT).any(|_| status() & 0x02 == 0)
+}
+
+fn read_data() -> Option<u8> {
+    if (0..TIMEOUT).any(|_| status() & 0x01 != 0) {
+        Some(unsafe { Port::<u8>::new(DATA_PORT).read() })
+    } else {
+        None
+    }
+}
+
+fn write_port(port: u16, value: u8) -> bool {
+    if !wait_input_empty() {
+        return false;
+    }
+    unsafe { Port::<u8>::new(port).write(value) };
+    true
+}
+
+fn send_keyboard(byte: u8) -> bool {
+    for _ in 0..3 {
+        if !write_port(DATA_PORT, byte) {
+            return false;
+        }
+        match read_data() {
+            Some(ACK) => return true,
+            Some(RESEND) => continue,
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn read_config() -> Option<u8> {
+    if !write_port(COMMAND_PORT, READ_CONFIG) {
+        return None;
+    }
+    read_data()
+}
+
+fn write_config(config: u8) -> bool {
+    write_port(COMMAND_PORT, WRITE_CONFIG) && write_port(DATA_PORT, config)
+}
+
+/// Switches the keyboard to scancode set 2 with controller translation off.
+/// Must run with interrupts disabled, otherwise the IRQ handler eats the ACKs.
+/// On failure the controller is left translating to set 1, without a controller
+/// nothing is touched.
+pub fn init() {
+    // without an i8042 the status port floats high and would never drain
+    if status() == 0xFF {
+        return;
+    }
+
+    // drop anything left over from the firmware
+    for _ in 0..TIMEOUT {
+        if status() & 0x01 == 0 {
+            break;
+        }
+        unsafe { Port::<u8>::new(DATA_PORT).read() };
+    }
+
+    let Some(config) = read_config() else {
+        return;
+    };
+
+    if !write_config(config & !CONFIG_TRANSLATION) {
+        return;
+    }
+
+    if send_keyboard(SET_SCANCODE_SET) && send_keyboard(0x02) {
+        SCANCODE_SET_2.store(true, Ordering::Relaxed);
+    } else {
+        write_config(config);
+    }
+}
+
+pub fn scancode_set_2_enabled() -> bool {
+    SCANCODE_SET_2.load(Ordering::Relaxed)
+}
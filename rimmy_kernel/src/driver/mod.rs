@@ -1,2 +1,12 @@
 pub mod keyboard;
-pub mod timer;
\ No newline at end of file
+pub mod nvram;
+pub mod pci;
+pub mod pci_driver;
+pub mod timer;
+
+/// Enumerates the PCI bus and hands each device to its registered driver.
+/// Drivers registered later with `pci_driver::register` are probed on registration.
+pub fn init() {
+    pci::init();
+    pci_driver::probe_all();
+}
@@ -0,0 +1,78 @@
+use core::ops::RangeInclusive;
+use x86_64::instructions::interrupts;
+use x86_64::instructions::port::Port;
+
+// battery-backed CMOS RAM that follows the RTC registers (0x0E..=0x7F)
+const NVRAM_START: u8 = 0x0E;
+pub const NVRAM_SIZE: usize = 114;
+
+/// NVRAM offsets of CMOS 0x10..=0x2F, the bytes the firmware checksums and the
+/// checksum itself at 0x2E/0x2F. Changing them gives a checksum error on next boot.
+pub const CHECKSUMMED: RangeInclusive<usize> = 0x02..=0x21;
+
+/// NVRAM offset of CMOS 0x32, the RTC century register on most firmware.
+pub const CENTURY: usize = 0x24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NvramError {
+    OutOfRange,
+    Protected,
+}
+
+/// Whether writes to `offset` are refused to keep the firmware's bytes intact.
+pub fn is_protected(offset: usize) -> bool {
+    CHECKSUMMED.contains(&offset) || offset == CENTURY
+}
+
+fn port_access(offset: u8, f: impl FnOnce(&mut Port<u8>)) -> Result<(), NvramError> {
+    if offset as usize >= NVRAM_SIZE {
+        return Err(NvramError::OutOfRange);
+    }
+
+    let mut addr = Port::<u8>::new(0x70);
+    let mut data = Port::<u8>::new(0x71);
+
+    // the address and data cycles must not be split by an interrupt touching the CMOS
+    interrupts::without_interrupts(|| {
+        unsafe { addr.write(NVRAM_START + offset) };
+        f(&mut data);
+    });
+    Ok(())
+}
+
+pub fn nvram_read(offset: u8) -> Result<u8, NvramError> {
+    let mut value = 0;
+    port_access(offset, |data| value = unsafe { data.read() })?;
+    Ok(value)
+}
+
+pub fn nvram_write(offset: u8, val: u8) -> Result<(), NvramError> {
+    if is_protected(offset as usize) {
+        return Err(NvramError::Protected);
+    }
+    port_access(offset, |data| unsafe { data.write(val) })
+}
+
+/// Reads NVRAM starting at `offset` into `buf`, returns the number of bytes read.
+pub fn read(offset: usize, buf: &mut [u8]) -> usize {
+    let count = buf.len().min(NVRAM_SIZE.saturating_sub(offset));
+    for (i, byte) in buf.iter_mut().take(count).enumerate() {
+        match nvram_read((offset + i) as u8) {
+            Ok(value) => *byte = value,
+            Err(_) => return i,
+        }
+    }
+    count
+}
+
+/// Writes `buf` to NVRAM starting at `offset`, returns the number of bytes written.
+/// Stops short at the first protected offset.
+pub fn write(offset: usize, buf: &[u8]) -> usize {
+    let count = buf.len().min(NVRAM_SIZE.saturating_sub(offset));
+    for (i, byte) in buf.iter().take(count).enumerate() {
+        if nvram_write((offset + i) as u8, *byte).is_err() {
+            return i;
+        }
+    }
+    count
+}
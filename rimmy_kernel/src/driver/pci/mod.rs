@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+use spin::Once;
+use x86_64::instructions::port::Port;
+use crate::{print, println};
+
+const CONFIG_ADDRESS: u16 = 0xCF8;
+const CONFIG_DATA: u16 = 0xCFC;
+
+static PCI_DEVICES: Once<Vec<PciDevice>> = Once::new();
+
+#[derive(Debug, Clone, Copy)]
+pub struct PciDevice {
+    pub bus: u8,
+    pub slot: u8,
+    pub func: u8,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class_code: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub rev_id: u8,
+    pub bars: [u32; 6],
+    pub irq_line: u8,
+}
+
+impl PciDevice {
+    fn read(bus: u8, slot: u8, func: u8) -> Option<Self> {
+        let id = read_config(bus, slot, func, 0x00);
+        let vendor_id = id as u16;
+        if vendor_id == 0xFFFF {
+            return None;
+        }
+
+        let class = read_config(bus, slot, func, 0x08);
+        let header_type = header_type(bus, slot, func) & 0x7F;
+
+        // general devices have 6 BARs, PCI-to-PCI bridges only 2, CardBus bridges none
+        let bar_count = match header_type {
+            0 => 6,
+            1 => 2,
+            _ => 0,
+        };
+        let mut bars = [0u32; 6];
+        for (i, bar) in bars.iter_mut().enumerate().take(bar_count) {
+            *bar = read_config(bus, slot, func, 0x10 + i as u8 * 4);
+        }
+
+        Some(Self {
+            bus,
+            slot,
+            func,
+            vendor_id,
+            device_id: (id >> 16) as u16,
+            class_code: (class >> 24) as u8,
+            subclass: (class >> 16) as u8,
+            prog_if: (class >> 8) as u8,
+            rev_id: class as u8,
+            bars,
+            irq_line: read_config(bus, slot, func, 0x3C) as u8,
+        })
+    }
+
+    pub fn read_config(&self, offset: u8) -> u32 {
+        read_config(self.bus, self.slot, self.func, offset)
+    }
+
+    pub fn write_config(&self, offset: u8, value: u32) {
+        write_config(self.bus, self.slot, self.func, offset, value)
+    }
+}
+
+fn config_address(bus: u8, slot: u8, func: u8, offset: u8) -> u32 {
+    0x8000_0000
+        | (bus as u32) << 16
+        | (slot as u32) << 11
+        | (func as u32) << 8
+        | (offset as u32 & 0xFC)
+}
+
+pub fn read_config(bus: u8, slot: u8, func: u8, offset: u8) -> u32 {
+    let mut addr = Port::<u32>::new(CONFIG_ADDRESS);
+    let mut data = Port::<u32>::new(CONFIG_DATA);
+
+    x86_64::instructions::interrupts::without_interrupts(|| unsafe {
+        addr.write(config_address(bus, slot, func, offset));
+        data.read()
+    })
+}
+
+pub fn write_config(bus: u8, slot: u8, func: u8, offset: u8, value: u32) {
+    let mut addr = Port::<u32>::new(CONFIG_ADDRESS);
+    let mut data = Port::<u32>::new(CONFIG_DATA);
+
+    x86_64::instructions::interrupts::without_interrupts(|| unsafe {
+        addr.write(config_address(bus, slot, func, offset));
+        data.write(value);
+    })
+}
+
+fn header_type(bus: u8, slot: u8, func: u8) -> u8 {
+    (read_config(bus, slot, func, 0x0C) >> 16) as u8
+}
+
+fn enumerate() -> Vec<PciDevice> {
+    let mut devices = Vec::new();
+
+    for bus in 0..=255u8 {
+        for slot in 0..32u8 {
+            let Some(device) = PciDevice::read(bus, slot, 0) else {
+                continue;
+            };
+            devices.push(device);
+
+            // only scan the other functions of multi-function devices
+            if header_type(bus, slot, 0) & 0x80 != 0 {
+                for func in 1..8u8 {
+                    if let Some(device) = PciDevice::read(bus, slot, func) {
+                        devices.push(device);
+                    }
+                }
+            }
+        }
+    }
+
+    devices
+}
+
+pub fn init() {
+    let devices = PCI_DEVICES.call_once(enumerate);
+
+    for device in devices {
+        println!(
+            "[pci] {:02x}:{:02x}.{} {:04x}:{:04x} class {:02x}{:02x}{:02x} irq {}",
+            device.bus,
+            device.slot,
+            device.func,
+            device.vendor_id,
+            device.device_id,
+            device.class_code,
+            device.subclass,
+            device.prog_if,
+            device.irq_line
+        );
+    }
+}
+
+pub fn devices() -> &'static [PciDevice] {
+    PCI_DEVICES.get().map(|devices| devices.as_slice()).unwrap_or(&[])
+}
+
+pub fn find_device(vendor_id: u16, device_id: u16) -> Option<&'static PciDevice> {
+    devices()
+        .iter()
+        .find(|device| device.vendor_id == vendor_id && device.device_id == device_id)
+}
+
+pub fn find_class(class_code: u8, subclass: u8) -> impl Iterator<Item = &'static PciDevice> {
+    devices()
+        .iter()
+        .filter(move |device| device.class_code == class_code && device.subclass == subclass)
+}
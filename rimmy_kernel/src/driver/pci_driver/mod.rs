@@ -0,0 +1,69 @@
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use spin::Mutex;
+use crate::driver::pci::{self, PciDevice};
+use crate::{print, println};
+
+static PCI_DRIVERS: Mutex<Vec<PciDriver>> = Mutex::new(Vec::new());
+static PROBED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy)]
+pub struct PciDriver {
+    pub name: &'static str,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub probe: fn(&PciDevice),
+}
+
+impl PciDriver {
+    fn matches(&self, device: &PciDevice) -> bool {
+        self.vendor_id == device.vendor_id && self.device_id == device.device_id
+    }
+}
+
+/// Adds `driver` to the registry. Once `probe_all` has run, the driver is
+/// probed right away against the boot-time devices no earlier driver claimed.
+pub fn register(driver: PciDriver) {
+    let unclaimed: Vec<&PciDevice> = {
+        let mut drivers = PCI_DRIVERS.lock();
+        let unclaimed = if PROBED.load(Ordering::Acquire) {
+            pci::devices()
+                .iter()
+                .filter(|device| driver.matches(device))
+                .filter(|device| !drivers.iter().any(|other| other.matches(device)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        drivers.push(driver);
+        unclaimed
+    };
+
+    for device in unclaimed {
+        bind(&driver, device);
+    }
+}
+
+/// Calls the first matching driver's `probe` for every enumerated PCI device.
+pub fn probe_all() {
+    // copy the list so probe callbacks are free to register more drivers
+    let drivers = {
+        let drivers = PCI_DRIVERS.lock();
+        PROBED.store(true, Ordering::Release);
+        drivers.clone()
+    };
+
+    for device in pci::devices() {
+        if let Some(driver) = drivers.iter().find(|driver| driver.matches(device)) {
+            bind(driver, device);
+        }
+    }
+}
+
+fn bind(driver: &PciDriver, device: &PciDevice) {
+    println!(
+        "[pci] {:02x}:{:02x}.{} bound to {}",
+        device.bus, device.slot, device.func, driver.name
+    );
+    (driver.probe)(device);
+}
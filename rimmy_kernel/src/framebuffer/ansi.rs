@@ -0,0 +1,160 @@
+const SYSTEM_COLORS: [u32; 16] = [
+    0x000000, 0xCD0000, 0x00CD00, 0xCDCD00, 0x0000EE, 0xCD00CD, 0x00CDCD, 0xE5E5E5,
+    0x7F7F7F, 0xFF0000, 0x00FF00, 0xFFFF00, 0x5C5CFF, 0xFF00FF, 0x00FFFF, 0xFFFFFF,
+];
+
+// further parameters of a CSI sequence are dropped
+const MAX_PARAMS: usize = 16;
+
+// xterm 256-color palette: 16 system colors, a 6x6x6 color cube and a 24 step grayscale ramp
+pub static PALETTE_256: [u32; 256] = build_palette();
+
+const fn build_palette() -> [u32; 256] {
+    let mut palette = [0u32; 256];
+    let mut i = 0;
+
+    while i < 16 {
+        palette[i] = SYSTEM_COLORS[i];
+        i += 1;
+    }
+
+    while i < 232 {
+        let n = i - 16;
+        let r = cube_level(n / 36);
+        let g = cube_level((n / 6) % 6);
+        let b = cube_level(n % 6);
+        palette[i] = (r << 16) | (g << 8) | b;
+        i += 1;
+    }
+
+    while i < 256 {
+        let level = 8 + (i as u32 - 232) * 10;
+        palette[i] = (level << 16) | (level << 8) | level;
+        i += 1;
+    }
+
+    palette
+}
+
+const fn cube_level(n: usize) -> u32 {
+    if n == 0 { 0 } else { 55 + n as u32 * 40 }
+}
+
+/// Parses the tail of an extended color SGR (`38;...` or `48;...`).
+/// Returns the color and the number of parameters consumed.
+pub fn extended_color(params: &[u16]) -> Option<(u32, usize)> {
+    match params {
+        [5, n, ..] if *n < 256 => Some((PALETTE_256[*n as usize], 2)),
+        [2, r, g, b, ..] => {
+            let (r, g, b) = (*r as u32 & 0xFF, *g as u32 & 0xFF, *b as u32 & 0xFF);
+            Some(((r << 16) | (g << 8) | b, 4))
+        }
+        _ => None,
+    }
+}
+
+/// Parameters of a finished CSI sequence, kept inline so parsing never allocates.
+#[derive(Clone, Copy)]
+pub struct CsiParams {
+    values: [u16; MAX_PARAMS],
+    len: usize,
+}
+
+impl CsiParams {
+    const fn new() -> Self {
+        Self {
+            values: [0; MAX_PARAMS],
+            len: 0,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[u16] {
+        &self.values[..self.len]
+    }
+}
+
+pub enum AnsiEvent {
+    Char(char),
+    Csi(char, CsiParams),
+    None,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+}
+
+pub struct AnsiParser {
+    state: State,
+    params: CsiParams,
+    current: Option<u16>,
+}
+
+impl Default for AnsiParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnsiParser {
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            params: CsiParams::new(),
+            current: None,
+        }
+    }
+
+    fn push_param(&mut self, value: u16) {
+        if self.params.len < MAX_PARAMS {
+            self.params.values[self.params.len] = value;
+            self.params.len += 1;
+        }
+    }
+
+    pub fn feed(&mut self, c: char) -> AnsiEvent {
+        match self.state {
+            State::Ground => {
+                if c == '\x1b' {
+                    self.state = State::Escape;
+                    AnsiEvent::None
+                } else {
+                    AnsiEvent::Char(c)
+                }
+            }
+            State::Escape => {
+                if c == '[' {
+                    self.params = CsiParams::new();
+                    self.current = None;
+                    self.state = State::Csi;
+                } else {
+                    self.state = State::Ground;
+                }
+                AnsiEvent::None
+            }
+            State::Csi => match c {
+                '0'..='9' => {
+                    let digit = c as u16 - '0' as u16;
+                    let value = self.current.unwrap_or(0);
+                    self.current = Some(value.saturating_mul(10).saturating_add(digit));
+                    AnsiEvent::None
+                }
+                ';' => {
+                    let value = self.current.take().unwrap_or(0);
+                    self.push_param(value);
+                    AnsiEvent::None
+                }
+                '\x40'..='\x7e' => {
+                    if let Some(value) = self.current.take() {
+                        self.push_param(value);
+                    }
+                    self.state = State::Ground;
+                    AnsiEvent::Csi(c, self.params)
+                }
+                _ => AnsiEvent::None,
+            },
+        }
+    }
+}
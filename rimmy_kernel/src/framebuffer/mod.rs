@@ -3,6 +3,7 @@ use limine::framebuffer::Framebuffer;
 use spin::Once;
 use crate::framebuffer::writer::Writer;
 
+pub mod ansi;
 pub mod font;
 pub mod writer;
 
@@ -60,7 +61,7 @@ pub fn init_framebuffer(fb: &Framebuffer) {
     }
 }
 
-pub fn clear_screen(clear_buffer: bool) {
+pub fn clear_screen() {
     let framebuffer = get_framebuffer();
     let color = 0x282C34u32;
 
@@ -82,9 +83,7 @@ pub fn clear_screen(clear_buffer: bool) {
     }
     get_writer().row_position = 0;
     get_writer().column_position = 0;
-    if clear_buffer {
-        get_writer().buffer_content.clear();
-    }
+    get_writer().reset_cells();
 }
 
 
@@ -112,7 +111,7 @@ macro_rules! print {
 
 #[macro_export]
 macro_rules! println {
-    () => ($crate::framebuffer::_print("\n"));
+    () => ($crate::framebuffer::_print(format_args!("\n")));
     ($($arg:tt)*) => (print!("{}\n", format_args!($($arg)*)));
 }
 
@@ -1,23 +1,23 @@
 use alloc::{vec, vec::Vec};
+use crate::framebuffer::ansi::{extended_color, AnsiEvent, AnsiParser, PALETTE_256};
 use crate::framebuffer::font::PSF_FONTS;
-use crate::framebuffer::{RimmyFrameBuffer, get_framebuffer, clear_screen};
+use crate::framebuffer::{RimmyFrameBuffer, get_framebuffer};
 use core::fmt;
 use core::fmt::Write;
 
-pub fn print(framebuffer: &'static RimmyFrameBuffer, x: usize, y: usize, color: u32, ascii: u8) {
+pub fn print(framebuffer: &'static RimmyFrameBuffer, x: usize, y: usize, color: u32, bg_color: u32, ascii: u8) {
     let fb_ptr = framebuffer.addr();
     let pitch = framebuffer.pitch();
     if let Some(font_bitmap) = PSF_FONTS.get(ascii as usize - 32) {
         for (row, &bitmap) in font_bitmap.iter().enumerate() {
             for col in 0..8 {
-                if (bitmap & (1 << (7 - col))) != 0 {
-                    let pixel_offset = ((y + row) * pitch as usize) + ((x + col) * 4);
-                    unsafe {
-                        fb_ptr
-                            .offset(pixel_offset as isize)
-                            .cast::<u32>()
-                            .write(color);
-                    }
+                let pixel_color = if (bitmap & (1 << (7 - col))) != 0 { color } else { bg_color };
+                let pixel_offset = ((y + row) * pitch as usize) + ((x + col) * 4);
+                unsafe {
+                    fb_ptr
+                        .offset(pixel_offset as isize)
+                        .cast::<u32>()
+                        .write(pixel_color);
                 }
             }
         }
@@ -44,30 +44,59 @@ pub fn clear_char(framebuffer: &'static RimmyFrameBuffer, x: usize, y: usize, co
     }
 }
 
+// what is drawn at a screen position, so scrolling can redraw without reading the framebuffer
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    c: u8,
+    color: u32,
+    bg_color: u32,
+}
+
 pub struct Writer {
     framebuffer: &'static RimmyFrameBuffer,
-    buffer: Vec<u64>,
-    pub buffer_content: Vec<Vec<char>>,
+    cells: Vec<Cell>,
     pub column_position: usize,
     pub row_position: usize,
     color: u32,
+    bg_color: u32,
+    default_color: u32,
+    default_bg_color: u32,
+    bold: bool,
+    reverse: bool,
+    // which of the 8 base colors is active, so bold can brighten it later
+    fg_index: Option<usize>,
+    ansi: AnsiParser,
 }
 
 impl Writer {
     pub fn new(color: u32) -> Self {
         Self {
             framebuffer: get_framebuffer(),
-            buffer_content: Vec::new(),
             column_position: 0,
             row_position: 0,
-            buffer: Vec::new(),
+            cells: Vec::new(),
             color,
+            bg_color: 0x282C34,
+            default_color: color,
+            default_bg_color: 0x282C34,
+            bold: false,
+            reverse: false,
+            fg_index: None,
+            ansi: AnsiParser::new(),
         }
     }
 
     pub fn write_char(&mut self, c: char) {
-        if self.buffer.is_empty() {
-            self.buffer = vec![0x282C34, self.framebuffer.width * self.framebuffer.height]
+        match self.ansi.feed(c) {
+            AnsiEvent::Char(c) => self.put_char(c),
+            AnsiEvent::Csi(action, params) => self.handle_csi(action, params.as_slice()),
+            AnsiEvent::None => {}
+        }
+    }
+
+    fn put_char(&mut self, c: char) {
+        if self.cells.is_empty() {
+            self.cells = vec![self.blank_cell(self.default_bg_color); self.columns() * self.rows()];
         }
         match c {
             '\n' => self.new_line(),
@@ -75,6 +104,7 @@ impl Writer {
                 clear_char(self.framebuffer, self.column_position * 8, self.row_position * 16, 0x282C34u32);
                 if self.column_position > 0 {
                     self.column_position -= 1;
+                    self.set_cell(self.column_position, self.row_position, self.blank_cell(0x282C34));
                 }
             },
             '\t' => {
@@ -83,57 +113,163 @@ impl Writer {
                     self.new_line();
                 }
             },
+            '\r' => self.column_position = 0,
+            _ if c.is_ascii_control() => {}
             _ => {
-                if let Some(current_buffer) = self.buffer_content.get_mut(self.row_position) {
-                    current_buffer.push(c);
-                } else {
-                    let mut current_buffer = Vec::new();
-                    current_buffer.push(c);
-                    self.buffer_content.push(current_buffer);
-                }
-
+                let (color, bg_color) = if self.reverse { (self.bg_color, self.color) } else { (self.color, self.bg_color) };
+                let cell = Cell { c: c as u8, color, bg_color };
+                self.set_cell(self.column_position, self.row_position, cell);
+                self.draw_cell(self.column_position, self.row_position, cell);
 
-                print(self.framebuffer, self.column_position * 8, self.row_position * 16, self.color, c as u8);
                 self.column_position += 1;
-                if self.column_position >= (self.framebuffer.width() / 8) as usize {
+                if self.column_position >= self.columns() {
                     self.new_line();
                 }
             }
         }
     }
 
+    fn handle_csi(&mut self, action: char, params: &[u16]) {
+        let count = params.first().copied().unwrap_or(1).max(1) as usize;
+        let columns = (self.framebuffer.width() / 8) as usize;
+
+        match action {
+            'm' => self.apply_sgr(params),
+            'K' => self.erase_to_end_of_line(),
+            'C' => self.column_position = (self.column_position + count).min(columns - 1),
+            'D' => self.column_position = self.column_position.saturating_sub(count),
+            _ => {}
+        }
+    }
+
+    fn erase_to_end_of_line(&mut self) {
+        let blank = self.blank_cell(self.bg_color);
+        for col in self.column_position..self.columns() {
+            self.set_cell(col, self.row_position, blank);
+            self.draw_cell(col, self.row_position, blank);
+        }
+    }
+
+    fn apply_sgr(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            self.reset_attributes();
+            return;
+        }
+
+        let mut i = 0;
+        while i < params.len() {
+            match params[i] {
+                0 => self.reset_attributes(),
+                1 => self.bold = true,
+                7 => self.reverse = true,
+                22 => self.bold = false,
+                27 => self.reverse = false,
+                n @ 30..=37 => self.fg_index = Some((n - 30) as usize),
+                38 => {
+                    if let Some((color, used)) = extended_color(&params[i + 1..]) {
+                        self.color = color;
+                        self.fg_index = None;
+                        i += used;
+                    }
+                }
+                39 => {
+                    self.color = self.default_color;
+                    self.fg_index = None;
+                }
+                n @ 40..=47 => self.bg_color = PALETTE_256[(n - 40) as usize],
+                48 => {
+                    if let Some((color, used)) = extended_color(&params[i + 1..]) {
+                        self.bg_color = color;
+                        i += used;
+                    }
+                }
+                49 => self.bg_color = self.default_bg_color,
+                n @ 90..=97 => {
+                    self.color = PALETTE_256[(n - 90) as usize + 8];
+                    self.fg_index = None;
+                }
+                n @ 100..=107 => self.bg_color = PALETTE_256[(n - 100) as usize + 8],
+                _ => {}
+            }
+            i += 1;
+        }
+
+        if let Some(index) = self.fg_index {
+            self.color = PALETTE_256[if self.bold { index + 8 } else { index }];
+        }
+    }
+
+    fn reset_attributes(&mut self) {
+        self.color = self.default_color;
+        self.bg_color = self.default_bg_color;
+        self.fg_index = None;
+        self.bold = false;
+        self.reverse = false;
+    }
+
     fn new_line(&mut self) {
         self.column_position = 0;
         self.row_position += 1;
 
-        let max_rows = (self.framebuffer.height() / 16) as usize;
+        let max_rows = self.rows();
 
         if self.row_position >= max_rows {
-            self.buffer_content.remove(0);
-
-            self.buffer_content.push(Vec::new());
-
-            self.redraw_screen();
+            self.scroll_up();
 
             self.row_position = max_rows - 1;
         }
     }
 
-    fn redraw_screen(&mut self) {
-        clear_screen(false);
+    /// Shifts the cells up a row and redraws only those that changed, reading the
+    /// framebuffer back instead would be slow on write-combined video memory.
+    fn scroll_up(&mut self) {
+        let columns = self.columns();
+        let blank = self.blank_cell(self.bg_color);
 
-        for (row_idx, line) in self.buffer_content.iter().enumerate() {
-            for (col_idx, c) in line.iter().enumerate() {
-                print(self.framebuffer, col_idx * 8, row_idx * 16, self.color, *c as u8);
+        for i in 0..self.cells.len() {
+            let below = self.cells.get(i + columns).copied().unwrap_or(blank);
+            if self.cells[i] != below {
+                self.cells[i] = below;
+                self.draw_cell(i % columns, i / columns, below);
             }
         }
     }
 
+    /// Forgets what is on screen, for when the framebuffer was cleared to the default background.
+    pub fn reset_cells(&mut self) {
+        let blank = self.blank_cell(self.default_bg_color);
+        self.cells.fill(blank);
+    }
+
+    fn blank_cell(&self, bg_color: u32) -> Cell {
+        Cell { c: b' ', color: bg_color, bg_color }
+    }
+
+    fn set_cell(&mut self, col: usize, row: usize, cell: Cell) {
+        let index = row * self.columns() + col;
+        if let Some(slot) = self.cells.get_mut(index) {
+            *slot = cell;
+        }
+    }
+
+    fn draw_cell(&self, col: usize, row: usize, cell: Cell) {
+        print(self.framebuffer, col * 8, row * 16, cell.color, cell.bg_color, cell.c);
+    }
+
+    fn columns(&self) -> usize {
+        (self.framebuffer.width() / 8) as usize
+    }
+
+    fn rows(&self) -> usize {
+        (self.framebuffer.height() / 16) as usize
+    }
+
     pub fn clear_line(&mut self) {
         let clear_color = 0x282C34u32;
 
         for i in 0..self.framebuffer.width() / 8 {
             clear_char(self.framebuffer, (i as usize + 1) * 8, self.row_position *  16, clear_color);
+            self.set_cell(i as usize, self.row_position, self.blank_cell(clear_color));
         }
     }
 
@@ -4,10 +4,17 @@ pub fn main() {
     let total_mem = crate::memory::allocator::get_total_heap_size();
     let used_mem = crate::memory::allocator::get_used_heap_size();
     let free_mem = total_mem - used_mem;
+    let total_ram = crate::memory::total_frames() * 4096;
+    let free_ram = crate::memory::available_frames() * 4096;
 
     println!("Memory Information:");
     println!("-------------------");
     println!("Total: {} KB", total_mem / 1024);
     println!("Used:  {} KB", used_mem / 1024);
     println!("Free:  {} KB", free_mem / 1024);
+    println!();
+    println!("Physical Memory:");
+    println!("-------------------");
+    println!("MemTotal: {} KB", total_ram / 1024);
+    println!("MemFree:  {} KB", free_ram / 1024);
 }
\ No newline at end of file
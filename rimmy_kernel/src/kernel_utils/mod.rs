@@ -1,3 +1,4 @@
 pub mod echo;
 pub mod meminfo;
-pub mod date;
\ No newline at end of file
+pub mod date;
+pub mod nvram;
\ No newline at end of file
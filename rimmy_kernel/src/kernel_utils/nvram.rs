@@ -0,0 +1,40 @@
+use crate::driver::nvram::{self, NVRAM_SIZE};
+use crate::{print, println};
+
+pub fn main(args: &[&str]) {
+    match args {
+        [] => dump(),
+        [offset, value] => {
+            let written = match (parse(offset), parse(value)) {
+                (Some(offset), Some(value)) if offset <= 0xFF && value <= 0xFF => {
+                    nvram::nvram_write(offset as u8, value as u8).is_ok()
+                }
+                _ => false,
+            };
+            if !written {
+                println!("nvram: invalid offset or value");
+            }
+        }
+        _ => println!("Usage: nvram [offset value]"),
+    }
+}
+
+fn dump() {
+    let mut buf = [0u8; NVRAM_SIZE];
+    nvram::read(0, &mut buf);
+
+    for (i, line) in buf.chunks(16).enumerate() {
+        print!("{:02x}:", i * 16);
+        for byte in line {
+            print!(" {:02x}", byte);
+        }
+        println!();
+    }
+}
+
+fn parse(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
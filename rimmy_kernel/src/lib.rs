@@ -14,6 +14,7 @@ pub mod buffer;
 extern crate alloc;
 
 use limine::framebuffer::Framebuffer;
+use spin::Mutex;
 use limine::response::{HhdmResponse, MemoryMapResponse};
 use x86_64::VirtAddr;
 use crate::framebuffer::{init_framebuffer, init_writer};
@@ -25,6 +26,7 @@ pub fn init(fb: &Framebuffer, hhdm_response: &HhdmResponse, memory_map_response:
     arch::x86_64::gdt::init();
     arch::x86_64::idt::init();
     arch::x86_64::idt::init_pics();
+    driver::keyboard::ps2::init();
     x86_64::instructions::interrupts::enable();
 
     let phys_mem_offset = VirtAddr::new(hhdm_response.offset());
@@ -34,6 +36,9 @@ pub fn init(fb: &Framebuffer, hhdm_response: &HhdmResponse, memory_map_response:
     };
 
     memory::allocator::init_heap(&mut mapper, &mut frame_allocator).expect("Failed to initialize heap");
+    memory::MAPPER.call_once(|| Mutex::new(mapper));
+    memory::FRAME_ALLOCATOR.call_once(|| Mutex::new(frame_allocator));
+    driver::init();
     executor::init_executor();
 }
 
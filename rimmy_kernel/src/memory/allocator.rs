@@ -1,36 +1,87 @@
-use linked_list_allocator::LockedHeap;
-use x86_64::structures::paging::{FrameAllocator, Mapper, Page, PageTableFlags, Size4KiB};
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+use linked_list_allocator::{align_up, LockedHeap};
+use x86_64::structures::paging::{FrameAllocator, FrameDeallocator, Mapper, Page, PageTableFlags, Size4KiB};
 use x86_64::structures::paging::mapper::MapToError;
 use x86_64::VirtAddr;
+use crate::memory::{total_frames, FRAME_ALLOCATOR, MAPPER};
 
 pub const HEAP_START: usize = 0x_4444_4444_0000;
 pub const HEAP_SIZE: usize = 300 * 1024;
+pub const HEAP_GROW_SIZE: usize = 1 << 20;
 
-#[global_allocator]
 pub static ALLOCATOR: LockedHeap = LockedHeap::empty();
 
-pub fn init_heap(
+#[global_allocator]
+static GLOBAL_ALLOCATOR: GrowableHeap = GrowableHeap;
+
+/// Wraps `ALLOCATOR` and grows the heap once before giving up on an allocation.
+pub struct GrowableHeap;
+
+unsafe impl GlobalAlloc for GrowableHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocation = ALLOCATOR.lock().allocate_first_fit(layout);
+        if let Ok(allocation) = allocation {
+            return allocation.as_ptr();
+        }
+
+        let additional_bytes = HEAP_GROW_SIZE.max(layout.size() + layout.align());
+        if grow_heap(additional_bytes).is_err() {
+            return ptr::null_mut();
+        }
+
+        ALLOCATOR
+            .lock()
+            .allocate_first_fit(layout)
+            .map_or(ptr::null_mut(), |allocation| allocation.as_ptr())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { ALLOCATOR.dealloc(ptr, layout) }
+    }
+}
+
+/// Maps the pages covering `start..start + size`. Returns how many bytes got mapped,
+/// together with the error that stopped it early, if any.
+fn map_heap_range(
     mapper: &mut impl Mapper<Size4KiB>,
-    frame_allocator: &mut impl FrameAllocator<Size4KiB>,
-) -> Result<(), MapToError<Size4KiB>> {
+    frame_allocator: &mut (impl FrameAllocator<Size4KiB> + FrameDeallocator<Size4KiB>),
+    start: usize,
+    size: usize,
+) -> (usize, Result<(), MapToError<Size4KiB>>) {
     let page_range = {
-        let heap_start = VirtAddr::new(HEAP_START as u64);
-        let head_end = heap_start + HEAP_SIZE as u64 - 1u64;
+        let heap_start = VirtAddr::new(start as u64);
+        let head_end = heap_start + size as u64 - 1u64;
         let heap_start_page = Page::containing_address(heap_start);
         let heap_end_page = Page::containing_address(head_end);
         Page::range_inclusive(heap_start_page, heap_end_page)
     };
 
+    let mut mapped = 0;
     for page in page_range {
-        let frame = frame_allocator
-            .allocate_frame()
-            .ok_or(MapToError::FrameAllocationFailed)?;
-        let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
-        unsafe {
-            mapper.map_to(page, frame, flags, frame_allocator)?.flush()
+        let Some(frame) = frame_allocator.allocate_frame() else {
+            return (mapped, Err(MapToError::FrameAllocationFailed));
         };
+        let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+        match unsafe { mapper.map_to(page, frame, flags, frame_allocator) } {
+            Ok(flush) => flush.flush(),
+            Err(err) => {
+                unsafe { frame_allocator.deallocate_frame(frame) };
+                return (mapped, Err(err));
+            }
+        }
+        mapped += page.size() as usize;
     }
 
+    (mapped, Ok(()))
+}
+
+pub fn init_heap(
+    mapper: &mut impl Mapper<Size4KiB>,
+    frame_allocator: &mut (impl FrameAllocator<Size4KiB> + FrameDeallocator<Size4KiB>),
+) -> Result<(), MapToError<Size4KiB>> {
+    map_heap_range(mapper, frame_allocator, HEAP_START, HEAP_SIZE).1?;
+
     unsafe {
         ALLOCATOR.lock().init(HEAP_START, HEAP_SIZE);
     };
@@ -38,10 +89,43 @@ pub fn init_heap(
     Ok(())
 }
 
+/// Maps `additional_bytes` (rounded up to whole pages) past the current heap top
+/// and hands them to the allocator. The heap is capped at a quarter of usable RAM.
+pub fn grow_heap(additional_bytes: usize) -> Result<(), MapToError<Size4KiB>> {
+    let (Some(mapper), Some(frame_allocator)) = (MAPPER.get(), FRAME_ALLOCATOR.get()) else {
+        return Err(MapToError::FrameAllocationFailed);
+    };
+    let mut mapper = mapper.lock();
+    let mut frame_allocator = frame_allocator.lock();
+
+    let additional_bytes = align_up(additional_bytes, 4096);
+    let (heap_top, heap_size) = {
+        let heap = ALLOCATOR.lock();
+        (heap.top(), heap.size())
+    };
+
+    let max_heap_size = total_frames() * 4096 / 4;
+    if heap_size + additional_bytes > max_heap_size {
+        return Err(MapToError::FrameAllocationFailed);
+    }
+
+    let (mapped, result) = map_heap_range(&mut *mapper, &mut *frame_allocator, heap_top, additional_bytes);
+
+    // hand over whatever did get mapped, otherwise the next grow starts at the
+    // same top and fails on the pages left behind
+    if mapped > 0 {
+        unsafe {
+            ALLOCATOR.lock().extend(mapped);
+        }
+    }
+
+    result
+}
+
 pub fn get_total_heap_size() -> usize {
     ALLOCATOR.lock().size()
 }
 
 pub fn get_used_heap_size() -> usize {
     ALLOCATOR.lock().used()
-}
\ No newline at end of file
+}
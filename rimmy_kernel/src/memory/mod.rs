@@ -1,20 +1,49 @@
 pub mod allocator;
 
-use x86_64::structures::paging::{FrameAllocator, OffsetPageTable, PageTable, PhysFrame, Size4KiB};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::{Mutex, Once};
+use x86_64::structures::paging::{FrameAllocator, FrameDeallocator, OffsetPageTable, PageTable, PhysFrame, Size4KiB};
 use x86_64::{PhysAddr, VirtAddr};
 use limine::memory_map::{Entry, EntryType};
 
+pub static MAPPER: Once<Mutex<OffsetPageTable<'static>>> = Once::new();
+pub static FRAME_ALLOCATOR: Once<Mutex<BootInfoFrameAllocator>> = Once::new();
+
+static TOTAL_FRAMES: AtomicUsize = AtomicUsize::new(0);
+static FREE_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of 4 KiB frames still available to the frame allocator.
+pub fn available_frames() -> usize {
+    FREE_FRAMES.load(Ordering::Relaxed)
+}
+
+/// Number of 4 KiB frames in the usable regions of the memory map.
+pub fn total_frames() -> usize {
+    TOTAL_FRAMES.load(Ordering::Relaxed)
+}
+
+// frames handed back after a failed mapping, reused before taking new ones
+const RECYCLED_FRAMES: usize = 16;
+
 pub struct BootInfoFrameAllocator {
     memory_map: &'static [&'static Entry],
     next: usize,
+    recycled: [Option<PhysFrame>; RECYCLED_FRAMES],
 }
 
 impl BootInfoFrameAllocator {
     pub unsafe fn init(memory_map: &'static [&Entry]) -> Self {
-        BootInfoFrameAllocator {
+        let allocator = BootInfoFrameAllocator {
             next: 0,
             memory_map,
-        }
+            recycled: [None; RECYCLED_FRAMES],
+        };
+
+        let frames = allocator.usable_frames().count();
+        TOTAL_FRAMES.store(frames, Ordering::Relaxed);
+        FREE_FRAMES.store(frames, Ordering::Relaxed);
+
+        allocator
     }
 
     fn usable_frames(&self) -> impl Iterator<Item = PhysFrame> {
@@ -34,12 +63,30 @@ impl BootInfoFrameAllocator {
 
 unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
     fn allocate_frame(&mut self) -> Option<PhysFrame> {
+        if let Some(frame) = self.recycled.iter_mut().find_map(Option::take) {
+            FREE_FRAMES.fetch_sub(1, Ordering::Relaxed);
+            return Some(frame);
+        }
+
         let frame = self.usable_frames().nth(self.next);
         self.next += 1;
+        if frame.is_some() {
+            FREE_FRAMES.fetch_sub(1, Ordering::Relaxed);
+        }
         frame
     }
 }
 
+impl FrameDeallocator<Size4KiB> for BootInfoFrameAllocator {
+    /// Keeps `frame` for the next allocation. Frames beyond the recycle slots are lost.
+    unsafe fn deallocate_frame(&mut self, frame: PhysFrame) {
+        if let Some(slot) = self.recycled.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(frame);
+            FREE_FRAMES.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
 #[allow(unsafe_op_in_unsafe_fn)]
 pub unsafe fn init(physical_memory_offset: VirtAddr) -> OffsetPageTable<'static> {
     let level_4_table = active_level_4_table(physical_memory_offset);